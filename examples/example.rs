@@ -9,16 +9,8 @@ fn main() {
     let mut writer = Writer::new();
     writer.running_status(true);
     let tempo: u32 = 60 * 1000000 / 102; //bpm:102
-    writer.push(Message::MetaEvent {
-        delta_time: 0,
-        event: MetaEvent::SetTempo,
-        data: [(tempo >> 16) as u8, (tempo >> 8) as u8, tempo as u8].to_vec(),
-    });
-    writer.push(Message::MetaEvent {
-        delta_time: 0,
-        event: MetaEvent::EndOfTrack,
-        data: Vec::new(),
-    });
+    writer.push(Message::meta_event(0, MetaEventPayload::SetTempo(tempo)));
+    writer.push(Message::meta_event(0, MetaEventPayload::EndOfTrack));
     writer.push(Message::TrackChange);
     writer.push(Message::MidiEvent {
         delta_time: 0,
@@ -36,11 +28,7 @@ fn main() {
             velocity: 0,
         },
     });
-    writer.push(Message::MetaEvent {
-        delta_time: 0,
-        event: MetaEvent::EndOfTrack,
-        data: Vec::new(),
-    });
+    writer.push(Message::meta_event(0, MetaEventPayload::EndOfTrack));
     writer.write("examples/example.mid").unwrap();
 
     // parse example
@@ -64,7 +52,7 @@ impl<'a> Handler for HogeHandler<'a> {
     }
     fn meta_event(&mut self, delta_time: u32, event: &MetaEvent, data: &Vec<u8>) {
         println!(
-            "delta time: {:>4}, Meta event: {}, data: {:?}",
+            "delta time: {:>4}, Meta event: {:?}, data: {:?}",
             delta_time,
             event,
             data
@@ -79,7 +67,7 @@ impl<'a> Handler for HogeHandler<'a> {
     }
     fn midi_event(&mut self, delta_time: u32, event: &MidiEvent) {
         println!(
-            "delta time: {:>4}, MIDI event: {}",
+            "delta time: {:>4}, MIDI event: {:?}",
             delta_time,
             event,
         );
@@ -92,7 +80,7 @@ impl<'a> Handler for HogeHandler<'a> {
     }
     fn sys_ex_event(&mut self, delta_time: u32, event: &SysExEvent, data: &Vec<u8>) {
         println!(
-            "delta time: {:>4}, System Exclusive Event: {}, data: {:?}",
+            "delta time: {:>4}, System Exclusive Event: {:?}, data: {:?}",
             delta_time,
             event,
             data
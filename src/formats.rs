@@ -0,0 +1,56 @@
+//! Low level binary formats shared by messages, the reader and the writer:
+//! the four byte chunk tags (`MThd`/`MTrk`) and the variable length
+//! quantity (VLQ) encoding used for delta times and meta/sysex data
+//! lengths.
+
+/// A delta time or length, stored as a plain `u32` and encoded on the wire
+/// as a variable length quantity (7 bits per byte, high bit set on every
+/// byte but the last).
+pub type VLQ = u32;
+
+pub trait VLQTool {
+    fn new(value: u32) -> Self;
+    fn binary(&self) -> Vec<u8>;
+    fn len(&self) -> usize;
+}
+impl VLQTool for VLQ {
+    fn new(value: u32) -> VLQ {
+        value
+    }
+    fn binary(&self) -> Vec<u8> {
+        let mut value = *self;
+        let mut binary: Vec<u8> = vec![(value & 0b0111_1111) as u8];
+        value >>= 7;
+        while value > 0 {
+            binary.push((value & 0b0111_1111) as u8 | 0b1000_0000);
+            value >>= 7;
+        }
+        binary.reverse();
+        binary
+    }
+    fn len(&self) -> usize {
+        self.binary().len()
+    }
+}
+
+/// Four byte chunk tag found at the start of every SMF chunk.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Tag {
+    Header,
+    Track,
+}
+impl Tag {
+    pub fn new(tag: &[u8; 4]) -> Option<Tag> {
+        match tag {
+            b"MThd" => Some(Tag::Header),
+            b"MTrk" => Some(Tag::Track),
+            _ => None,
+        }
+    }
+    pub fn binary(&self) -> [u8; 4] {
+        match *self {
+            Tag::Header => *b"MThd",
+            Tag::Track => *b"MTrk",
+        }
+    }
+}
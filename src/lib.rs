@@ -0,0 +1,7 @@
+//! ghakuf: a Standard MIDI File reader/writer library.
+
+pub mod formats;
+pub mod messages;
+pub mod reader;
+pub mod smf;
+pub mod writer;
@@ -19,6 +19,7 @@ pub enum Message {
         event: SysExEvent,
         data: Vec<u8>,
     },
+    SystemEvent { delta_time: VLQ, event: SystemEvent },
     TrackChange,
 }
 impl Message {
@@ -62,10 +63,37 @@ impl Message {
                     }
                 }
             }
+            SystemEvent {
+                delta_time,
+                ref event,
+            } => {
+                binary.append(&mut delta_time.binary());
+                binary.append(&mut event.binary());
+            }
             TrackChange => binary.append(&mut Tag::Track.binary().to_vec()),
         }
         binary
     }
+    /// Builds a `Message::MetaEvent` from a decoded [`MetaEventPayload`],
+    /// so callers don't have to hand-assemble the raw `event`/`data` pair.
+    pub fn meta_event(delta_time: VLQ, payload: MetaEventPayload) -> Message {
+        let (event, data) = payload.to_raw();
+        Message::MetaEvent {
+            delta_time: delta_time,
+            event: event,
+            data: data,
+        }
+    }
+    /// Decodes a `Message::MetaEvent`'s raw bytes into a [`MetaEventPayload`],
+    /// or `None` if this isn't a `MetaEvent`.
+    pub fn meta_event_payload(&self) -> Option<MetaEventPayload> {
+        match *self {
+            Message::MetaEvent { ref event, ref data, .. } => {
+                Some(MetaEventPayload::from_raw(event, data))
+            }
+            _ => None,
+        }
+    }
     pub fn len(&self) -> usize {
         use messages::Message::*;
         match *self {
@@ -93,6 +121,10 @@ impl Message {
                             },
                     ).len() + data.len()
             }
+            SystemEvent {
+                delta_time,
+                ref event,
+            } => delta_time.len() + event.len(),
             TrackChange => Tag::Track.binary().len(),
         }
     }
@@ -172,12 +204,240 @@ impl MessageTool for MetaEvent {
     }
 }
 
+/// Decoded form of a `MetaEvent`'s payload bytes. `MetaEvent`/`data` stays
+/// the wire representation consumed by `Message::binary()`; this is a
+/// convenience layer on top of it so callers don't have to hand-assemble
+/// (or re-parse) the raw byte encodings documented in the SMF spec.
+///
+/// `Raw` is the fallback for meta events whose payload doesn't match the
+/// expected length for its type (malformed input) or whose event type has
+/// no typed representation here (e.g. `SequencerSpecificMetaEvent`).
+#[derive(PartialEq, Clone, Debug)]
+pub enum MetaEventPayload {
+    SequenceNumber(u16),
+    TextEvent(String),
+    CopyrightNotice(String),
+    SequenceOrTrackName(String),
+    InstrumentName(String),
+    Lyric(String),
+    Marker(String),
+    CuePoint(String),
+    MIDIChannelPrefix(u8),
+    EndOfTrack,
+    SetTempo(u32),
+    SMPTEOffset {
+        hours: u8,
+        minutes: u8,
+        seconds: u8,
+        frames: u8,
+        subframes: u8,
+    },
+    TimeSignature {
+        numerator: u8,
+        denominator_pow2: u8,
+        clocks_per_click: u8,
+        notated_32nds_per_beat: u8,
+    },
+    KeySignature { sharps_flats: i8, minor: bool },
+    Raw(MetaEvent, Vec<u8>),
+}
+impl MetaEventPayload {
+    pub fn from_raw(event: &MetaEvent, data: &[u8]) -> MetaEventPayload {
+        use messages::MetaEvent::*;
+        match *event {
+            SequenceNumber if data.len() == 2 => {
+                MetaEventPayload::SequenceNumber(((data[0] as u16) << 8) | data[1] as u16)
+            }
+            TextEvent => MetaEventPayload::TextEvent(String::from_utf8_lossy(data).into_owned()),
+            CopyrightNotice => {
+                MetaEventPayload::CopyrightNotice(String::from_utf8_lossy(data).into_owned())
+            }
+            SequenceOrTrackName => {
+                MetaEventPayload::SequenceOrTrackName(String::from_utf8_lossy(data).into_owned())
+            }
+            InstrumentName => {
+                MetaEventPayload::InstrumentName(String::from_utf8_lossy(data).into_owned())
+            }
+            Lyric => MetaEventPayload::Lyric(String::from_utf8_lossy(data).into_owned()),
+            Marker => MetaEventPayload::Marker(String::from_utf8_lossy(data).into_owned()),
+            CuePoint => MetaEventPayload::CuePoint(String::from_utf8_lossy(data).into_owned()),
+            MIDIChannelPrefix if data.len() == 1 => MetaEventPayload::MIDIChannelPrefix(data[0]),
+            EndOfTrack if data.is_empty() => MetaEventPayload::EndOfTrack,
+            SetTempo if data.len() == 3 => {
+                MetaEventPayload::SetTempo(
+                    ((data[0] as u32) << 16) | ((data[1] as u32) << 8) | data[2] as u32,
+                )
+            }
+            SMTPEOffset if data.len() == 5 => {
+                MetaEventPayload::SMPTEOffset {
+                    hours: data[0],
+                    minutes: data[1],
+                    seconds: data[2],
+                    frames: data[3],
+                    subframes: data[4],
+                }
+            }
+            TimeSignature if data.len() == 4 => {
+                MetaEventPayload::TimeSignature {
+                    numerator: data[0],
+                    denominator_pow2: data[1],
+                    clocks_per_click: data[2],
+                    notated_32nds_per_beat: data[3],
+                }
+            }
+            KeySignature if data.len() == 2 => {
+                MetaEventPayload::KeySignature {
+                    sharps_flats: data[0] as i8,
+                    minor: data[1] != 0,
+                }
+            }
+            _ => MetaEventPayload::Raw(event.clone(), data.to_vec()),
+        }
+    }
+    pub fn to_raw(&self) -> (MetaEvent, Vec<u8>) {
+        use messages::MetaEventPayload::*;
+        match *self {
+            SequenceNumber(value) => {
+                (MetaEvent::SequenceNumber, vec![(value >> 8) as u8, value as u8])
+            }
+            TextEvent(ref text) => (MetaEvent::TextEvent, text.clone().into_bytes()),
+            CopyrightNotice(ref text) => (MetaEvent::CopyrightNotice, text.clone().into_bytes()),
+            SequenceOrTrackName(ref text) => {
+                (MetaEvent::SequenceOrTrackName, text.clone().into_bytes())
+            }
+            InstrumentName(ref text) => (MetaEvent::InstrumentName, text.clone().into_bytes()),
+            Lyric(ref text) => (MetaEvent::Lyric, text.clone().into_bytes()),
+            Marker(ref text) => (MetaEvent::Marker, text.clone().into_bytes()),
+            CuePoint(ref text) => (MetaEvent::CuePoint, text.clone().into_bytes()),
+            MIDIChannelPrefix(channel) => (MetaEvent::MIDIChannelPrefix, vec![channel]),
+            EndOfTrack => (MetaEvent::EndOfTrack, Vec::new()),
+            SetTempo(microseconds_per_quarter_note) => {
+                (
+                    MetaEvent::SetTempo,
+                    vec![
+                        (microseconds_per_quarter_note >> 16) as u8,
+                        (microseconds_per_quarter_note >> 8) as u8,
+                        microseconds_per_quarter_note as u8,
+                    ],
+                )
+            }
+            SMPTEOffset { hours, minutes, seconds, frames, subframes } => {
+                (MetaEvent::SMTPEOffset, vec![hours, minutes, seconds, frames, subframes])
+            }
+            TimeSignature { numerator, denominator_pow2, clocks_per_click, notated_32nds_per_beat } => {
+                (
+                    MetaEvent::TimeSignature,
+                    vec![numerator, denominator_pow2, clocks_per_click, notated_32nds_per_beat],
+                )
+            }
+            KeySignature { sharps_flats, minor } => {
+                (MetaEvent::KeySignature, vec![sharps_flats as u8, minor as u8])
+            }
+            Raw(ref event, ref data) => (event.clone(), data.clone()),
+        }
+    }
+}
+
+/// Common controllers sent via `ControlChange` (controller numbers below
+/// 120; 120-127 are channel-mode messages, see `ChannelMode`), so callers
+/// don't have to memorize the raw controller numbers.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Controller {
+    BankSelect,
+    Modulation,
+    DataEntry,
+    Volume,
+    Pan,
+    Expression,
+    Sustain,
+    Unknown(u8),
+}
+impl Controller {
+    pub fn new(control: u8) -> Controller {
+        match control {
+            0x00 | 0x20 => Controller::BankSelect,
+            0x01 | 0x21 => Controller::Modulation,
+            0x06 | 0x26 => Controller::DataEntry,
+            0x07 | 0x27 => Controller::Volume,
+            0x0a | 0x2a => Controller::Pan,
+            0x0b | 0x2b => Controller::Expression,
+            0x40 => Controller::Sustain,
+            _ => Controller::Unknown(control),
+        }
+    }
+}
+
+/// The channel-mode messages, i.e. `ControlChange` with controller numbers
+/// 120-127. Unlike ordinary controllers, these affect how the receiving
+/// channel responds to notes and other controllers rather than shaping a
+/// sound, so they get their own typed `MidiEvent::ChannelMode` variant.
+///
+/// Every variant keeps the data byte actually seen on the wire, even the
+/// ones with a spec-mandated canonical value (e.g. `AllSoundOff` is always
+/// sent with data 0): re-encoding a message parsed from a malformed or
+/// unusual source must reproduce its bytes exactly rather than silently
+/// normalizing them to the canonical value.
+#[derive(PartialEq, Clone, Debug)]
+pub enum ChannelMode {
+    AllSoundOff { data: u8 },
+    ResetAllControllers { data: u8 },
+    /// Data byte 127 connects the local keyboard/controls, 0 disconnects
+    /// them so the channel only responds to MIDI in; `local_control_on`
+    /// interprets `data` against that convention.
+    LocalControl { data: u8 },
+    AllNotesOff { data: u8 },
+    OmniOff { data: u8 },
+    OmniOn { data: u8 },
+    /// Switches to monophonic operation; `channel_count` is the number of
+    /// channels to assign monophonically (0 means "as many as needed").
+    MonoMode { channel_count: u8 },
+    PolyMode { data: u8 },
+}
+impl ChannelMode {
+    pub fn new(control: u8, data: u8) -> Option<ChannelMode> {
+        match control {
+            120 => Some(ChannelMode::AllSoundOff { data: data }),
+            121 => Some(ChannelMode::ResetAllControllers { data: data }),
+            122 => Some(ChannelMode::LocalControl { data: data }),
+            123 => Some(ChannelMode::AllNotesOff { data: data }),
+            124 => Some(ChannelMode::OmniOff { data: data }),
+            125 => Some(ChannelMode::OmniOn { data: data }),
+            126 => Some(ChannelMode::MonoMode { channel_count: data }),
+            127 => Some(ChannelMode::PolyMode { data: data }),
+            _ => None,
+        }
+    }
+    /// The `(controller, data)` pair this channel-mode message round-trips
+    /// to/from on the wire, byte for byte.
+    pub fn control_data(&self) -> (u8, u8) {
+        match *self {
+            ChannelMode::AllSoundOff { data } => (120, data),
+            ChannelMode::ResetAllControllers { data } => (121, data),
+            ChannelMode::LocalControl { data } => (122, data),
+            ChannelMode::AllNotesOff { data } => (123, data),
+            ChannelMode::OmniOff { data } => (124, data),
+            ChannelMode::OmniOn { data } => (125, data),
+            ChannelMode::MonoMode { channel_count } => (126, channel_count),
+            ChannelMode::PolyMode { data } => (127, data),
+        }
+    }
+    /// For `LocalControl`, whether `data` means "on" (127) per the
+    /// convention above; `None` for every other variant.
+    pub fn local_control_on(&self) -> Option<bool> {
+        match *self {
+            ChannelMode::LocalControl { data } => Some(data == 127),
+            _ => None,
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub enum MidiEvent {
     NoteOff { ch: u8, note: u8, velocity: u8 },
     NoteOn { ch: u8, note: u8, velocity: u8 },
     PolyphonicKeyPressure { ch: u8, note: u8, velocity: u8 },
     ControlChange { ch: u8, control: u8, data: u8 },
+    ChannelMode { ch: u8, mode: ChannelMode },
     ProgramChange { ch: u8, program: u8 },
     ChannelPressure { ch: u8, pressure: u8 },
     PitchBendChange { ch: u8, data: i16 },
@@ -233,10 +493,16 @@ impl MidiEventBuilder {
                 }
             }
             0xb0 => {
-                MidiEvent::ControlChange {
-                    ch: self.status & 0x0f,
-                    control: self.data[0],
-                    data: self.data[1],
+                let (control, data) = (self.data[0], self.data[1]);
+                match ChannelMode::new(control, data) {
+                    Some(mode) => MidiEvent::ChannelMode { ch: self.status & 0x0f, mode: mode },
+                    None => {
+                        MidiEvent::ControlChange {
+                            ch: self.status & 0x0f,
+                            control: control,
+                            data: data,
+                        }
+                    }
                 }
             }
             0xc0 => {
@@ -263,6 +529,31 @@ impl MidiEventBuilder {
         }
     }
 }
+impl MidiEvent {
+    /// The channel (0-15) this event belongs to.
+    pub fn channel(&self) -> u8 {
+        use messages::MidiEvent::*;
+        match *self {
+            NoteOff { ch, .. } |
+            NoteOn { ch, .. } |
+            PolyphonicKeyPressure { ch, .. } |
+            ControlChange { ch, .. } |
+            ChannelMode { ch, .. } |
+            ProgramChange { ch, .. } |
+            ChannelPressure { ch, .. } |
+            PitchBendChange { ch, .. } |
+            Unknown { ch } => ch,
+        }
+    }
+    /// The typed controller this `ControlChange` targets, or `None` if
+    /// this isn't a `ControlChange` (e.g. it's already a `ChannelMode`).
+    pub fn controller(&self) -> Option<Controller> {
+        match *self {
+            MidiEvent::ControlChange { control, .. } => Some(Controller::new(control)),
+            _ => None,
+        }
+    }
+}
 impl MessageTool for MidiEvent {
     fn binary(&self) -> Vec<u8> {
         use messages::MidiEvent::*;
@@ -273,6 +564,10 @@ impl MessageTool for MidiEvent {
                 vec![self.status_byte(), note, velocity]
             }
             ControlChange { control, data, .. } => vec![self.status_byte(), control, data],
+            ChannelMode { ref mode, .. } => {
+                let (control, data) = mode.control_data();
+                vec![self.status_byte(), control, data]
+            }
             ProgramChange { program, .. } => vec![self.status_byte(), program],
             ChannelPressure { pressure, .. } => vec![self.status_byte(), pressure],
             MidiEvent::PitchBendChange { data, .. } => {
@@ -293,6 +588,7 @@ impl MessageTool for MidiEvent {
             NoteOn { .. } |
             PolyphonicKeyPressure { .. } |
             ControlChange { .. } |
+            ChannelMode { .. } |
             PitchBendChange { .. } => 3,
             ProgramChange { .. } |
             ChannelPressure { .. } => 2,
@@ -306,6 +602,7 @@ impl MessageTool for MidiEvent {
             NoteOn { ch, .. } => 0x90 | (ch & 0x0f),
             PolyphonicKeyPressure { ch, .. } => 0xa0 | (ch & 0x0f),
             ControlChange { ch, .. } => 0xb0 | (ch & 0x0f),
+            ChannelMode { ch, .. } => 0xb0 | (ch & 0x0f),
             ProgramChange { ch, .. } => 0xc0 | (ch & 0x0f),
             ChannelPressure { ch, .. } => 0xd0 | (ch & 0x0f),
             PitchBendChange { ch, .. } => 0xe0 | (ch & 0x0f),
@@ -345,3 +642,163 @@ impl MessageTool for SysExEvent {
         }
     }
 }
+
+/// System Common messages (0xF1-0xF6) and System Real-Time messages
+/// (0xF8, 0xFA-0xFC, 0xFE), i.e. the part of the 0xF0-0xFF status range not
+/// already covered by `SysExEvent` (0xF0/0xF7) or `MetaEvent` (0xFF inside
+/// a track chunk). Real-Time messages carry no data bytes and, on a live
+/// stream, may be injected between the status and data bytes of another
+/// message without disturbing it; a `Reader` is responsible for enforcing
+/// that, since it depends on parse context rather than anything encoded
+/// here.
+#[derive(PartialEq, Clone, Debug)]
+pub enum SystemEvent {
+    MIDITimeCodeQuarterFrame { message_type: u8, value: u8 },
+    SongPositionPointer { position: u16 },
+    SongSelect { song: u8 },
+    TuneRequest,
+    TimingClock,
+    Start,
+    Continue,
+    Stop,
+    ActiveSensing,
+    Unknown { status: u8, data: Vec<u8> },
+}
+impl MessageTool for SystemEvent {
+    fn binary(&self) -> Vec<u8> {
+        use messages::SystemEvent::*;
+        match *self {
+            MIDITimeCodeQuarterFrame { message_type, value } => {
+                vec![self.status_byte(), ((message_type & 0x07) << 4) | (value & 0x0f)]
+            }
+            SongPositionPointer { position } => {
+                vec![
+                    self.status_byte(),
+                    (position & 0x7f) as u8,
+                    ((position >> 7) & 0x7f) as u8,
+                ]
+            }
+            SongSelect { song } => vec![self.status_byte(), song],
+            TuneRequest | TimingClock | Start | Continue | Stop | ActiveSensing => {
+                vec![self.status_byte()]
+            }
+            Unknown { ref data, .. } => {
+                let mut binary = vec![self.status_byte()];
+                binary.extend_from_slice(data);
+                binary
+            }
+        }
+    }
+    fn len(&self) -> usize {
+        self.binary().len()
+    }
+    fn status_byte(&self) -> u8 {
+        use messages::SystemEvent::*;
+        match *self {
+            MIDITimeCodeQuarterFrame { .. } => 0xf1,
+            SongPositionPointer { .. } => 0xf2,
+            SongSelect { .. } => 0xf3,
+            TuneRequest => 0xf6,
+            TimingClock => 0xf8,
+            Start => 0xfa,
+            Continue => 0xfb,
+            Stop => 0xfc,
+            ActiveSensing => 0xfe,
+            Unknown { status, .. } => status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_round_trips(event: MetaEvent, payload: MetaEventPayload) {
+        let (decoded_event, data) = payload.to_raw();
+        assert_eq!(decoded_event, event);
+        assert_eq!(MetaEventPayload::from_raw(&event, &data), payload);
+    }
+
+    #[test]
+    fn set_tempo_round_trips() {
+        assert_round_trips(MetaEvent::SetTempo, MetaEventPayload::SetTempo(500_000));
+    }
+
+    #[test]
+    fn time_signature_round_trips() {
+        assert_round_trips(
+            MetaEvent::TimeSignature,
+            MetaEventPayload::TimeSignature {
+                numerator: 3,
+                denominator_pow2: 2,
+                clocks_per_click: 24,
+                notated_32nds_per_beat: 8,
+            },
+        );
+    }
+
+    #[test]
+    fn key_signature_round_trips_negative_sharps_flats() {
+        assert_round_trips(
+            MetaEvent::KeySignature,
+            MetaEventPayload::KeySignature { sharps_flats: -3, minor: true },
+        );
+    }
+
+    #[test]
+    fn smpte_offset_round_trips() {
+        assert_round_trips(
+            MetaEvent::SMTPEOffset,
+            MetaEventPayload::SMPTEOffset {
+                hours: 1,
+                minutes: 2,
+                seconds: 3,
+                frames: 4,
+                subframes: 5,
+            },
+        );
+    }
+
+    #[test]
+    fn text_events_round_trip() {
+        assert_round_trips(MetaEvent::TextEvent, MetaEventPayload::TextEvent("hello".to_string()));
+        assert_round_trips(
+            MetaEvent::SequenceOrTrackName,
+            MetaEventPayload::SequenceOrTrackName("track 1".to_string()),
+        );
+    }
+
+    #[test]
+    fn midi_channel_prefix_and_sequence_number_round_trip() {
+        assert_round_trips(MetaEvent::MIDIChannelPrefix, MetaEventPayload::MIDIChannelPrefix(3));
+        assert_round_trips(MetaEvent::SequenceNumber, MetaEventPayload::SequenceNumber(42));
+    }
+
+    #[test]
+    fn malformed_payload_falls_back_to_raw_instead_of_panicking() {
+        let data = vec![1, 2]; // SetTempo expects 3 bytes, not 2
+        let payload = MetaEventPayload::from_raw(&MetaEvent::SetTempo, &data);
+        assert_eq!(payload, MetaEventPayload::Raw(MetaEvent::SetTempo, data));
+    }
+
+    #[test]
+    fn malformed_end_of_track_falls_back_to_raw() {
+        let data = vec![1, 2, 3]; // EndOfTrack expects no data
+        let payload = MetaEventPayload::from_raw(&MetaEvent::EndOfTrack, &data);
+        assert_eq!(payload, MetaEventPayload::Raw(MetaEvent::EndOfTrack, data));
+    }
+
+    #[test]
+    fn message_meta_event_constructor_and_accessor_round_trip() {
+        let message = Message::meta_event(0, MetaEventPayload::SetTempo(300_000));
+        assert_eq!(message.meta_event_payload(), Some(MetaEventPayload::SetTempo(300_000)));
+    }
+
+    #[test]
+    fn channel_mode_preserves_exact_data_byte() {
+        // AllSoundOff's canonical data byte is 0, but a real-world sender
+        // might not be spec-compliant; the round trip must not normalize it.
+        let mode = ChannelMode::new(120, 5).unwrap();
+        assert_eq!(mode.control_data(), (120, 5));
+    }
+}
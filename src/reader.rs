@@ -0,0 +1,628 @@
+//! Standard MIDI File reader: parses a `.mid` file chunk by chunk and
+//! dispatches decoded [`Message`](::messages)s to a user-supplied
+//! [`Handler`].
+
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+use formats::*;
+use messages::*;
+
+/// Callbacks invoked by [`Reader::read`] as it walks the SMF. `header`,
+/// `meta_event`, `midi_event`, `sys_ex_event` and `track_change` must be
+/// implemented; the `*_at` and `system_event` callbacks have empty default
+/// bodies, so a handler only implements the ones it cares about.
+pub trait Handler {
+    fn header(&mut self, format: u16, track: u16, time_base: u16);
+    fn meta_event(&mut self, delta_time: u32, event: &MetaEvent, data: &Vec<u8>);
+    fn midi_event(&mut self, delta_time: u32, event: &MidiEvent);
+    fn sys_ex_event(&mut self, delta_time: u32, event: &SysExEvent, data: &Vec<u8>);
+    fn track_change(&mut self);
+
+    /// Like [`meta_event`](Handler::meta_event), but additionally carries
+    /// the event's absolute tick (ticks elapsed since the start of the
+    /// current track) and the wall-clock time in seconds, derived from the
+    /// running tempo map. Defaults to doing nothing, so implementing it is
+    /// opt-in.
+    fn meta_event_at(&mut self, _tick: u32, _seconds: f64, _event: &MetaEvent, _data: &Vec<u8>) {}
+    /// See [`meta_event_at`](Handler::meta_event_at).
+    fn midi_event_at(&mut self, _tick: u32, _seconds: f64, _event: &MidiEvent) {}
+    /// See [`meta_event_at`](Handler::meta_event_at).
+    fn sys_ex_event_at(&mut self, _tick: u32, _seconds: f64, _event: &SysExEvent, _data: &Vec<u8>) {}
+
+    /// `delta_time` is 0 for a Real-Time message received while interleaved
+    /// inside another message's data bytes.
+    fn system_event(&mut self, _delta_time: u32, _event: &SystemEvent) {}
+    /// See [`meta_event_at`](Handler::meta_event_at).
+    fn system_event_at(&mut self, _tick: u32, _seconds: f64, _event: &SystemEvent) {}
+}
+
+/// System Real-Time messages may appear between the status and data bytes
+/// of any other message without disturbing it (and without affecting
+/// running status).
+fn is_system_real_time(status: u8) -> bool {
+    match status {
+        0xf8 | 0xfa | 0xfb | 0xfc | 0xfe => true,
+        _ => false,
+    }
+}
+
+#[derive(Debug)]
+pub enum ReaderError {
+    Io(io::Error),
+    InvalidTag { tag: [u8; 4] },
+    UnexpectedEnd,
+}
+impl From<io::Error> for ReaderError {
+    fn from(error: io::Error) -> ReaderError {
+        ReaderError::Io(error)
+    }
+}
+
+const DEFAULT_TEMPO: u32 = 500_000; // 120 bpm, microseconds per quarter note
+
+/// A no-op `Handler` that only records `(tick, tempo)` pairs from `SetTempo`
+/// meta events, in file order, merged across every track. Used by
+/// `build_tempo_map` to learn the whole file's tempo timeline before the
+/// real read pass, since ticks (unlike the events that set them) are
+/// synchronized across tracks in a format-1 file.
+struct TempoCollector {
+    changes: Vec<(u32, u32)>,
+}
+impl Handler for TempoCollector {
+    fn header(&mut self, _format: u16, _track: u16, _time_base: u16) {}
+    fn meta_event(&mut self, _delta_time: u32, _event: &MetaEvent, _data: &Vec<u8>) {}
+    fn midi_event(&mut self, _delta_time: u32, _event: &MidiEvent) {}
+    fn sys_ex_event(&mut self, _delta_time: u32, _event: &SysExEvent, _data: &Vec<u8>) {}
+    fn track_change(&mut self) {}
+    fn meta_event_at(&mut self, tick: u32, _seconds: f64, event: &MetaEvent, data: &Vec<u8>) {
+        if *event == MetaEvent::SetTempo && data.len() == 3 {
+            let tempo = ((data[0] as u32) << 16) | ((data[1] as u32) << 8) | data[2] as u32;
+            self.changes.push((tick, tempo));
+        }
+    }
+}
+
+/// Scans every track of `bytes` for `SetTempo` events and returns a
+/// tick-ordered timeline of `(tick, tempo)`, seeded with the default tempo
+/// at tick 0. Ticks are track-relative, but since every track of a format-1
+/// SMF starts at tick 0 and plays in lockstep, a tick value means the same
+/// point in the song no matter which track set the tempo there.
+fn build_tempo_map(bytes: &[u8]) -> Result<Vec<(u32, u32)>, ReaderError> {
+    let mut collector = TempoCollector { changes: Vec::new() };
+    {
+        let mut scanner = Reader::from_bytes(&mut collector, bytes)?;
+        scanner.read_header_and_tracks(false)?;
+    }
+    let mut map = vec![(0u32, DEFAULT_TEMPO)];
+    map.extend(collector.changes);
+    map.sort_by_key(|&(tick, _)| tick);
+    Ok(map)
+}
+
+pub struct Reader<'a> {
+    handler: &'a mut Handler,
+    buf: Vec<u8>,
+    pos: usize,
+    time_base: u16,
+    running_status: u8,
+    /// Ticks elapsed since the most recent `TrackChange`.
+    tick: u32,
+    /// Wall-clock seconds elapsed since the most recent `TrackChange`,
+    /// derived from `tempo_map` as ticks are consumed.
+    seconds: f64,
+    /// Tick-ordered `(tick, tempo)` timeline covering the whole file,
+    /// built once up front by `build_tempo_map` so that a tempo change
+    /// recorded in one track (typically the conductor track) is honored
+    /// when computing `seconds` for events in every other track, even
+    /// mid-delta.
+    tempo_map: Vec<(u32, u32)>,
+    /// Index into `tempo_map` of the tempo segment `tick` currently falls
+    /// in. Reset to 0 at each `TrackChange`, since `tick` restarts there
+    /// too; only ever moves forward within a track, as `tick` is
+    /// monotonic.
+    tempo_idx: usize,
+}
+impl<'a> Reader<'a> {
+    pub fn new<P: AsRef<Path>>(handler: &'a mut Handler, path: P) -> Result<Reader<'a>, ReaderError> {
+        Reader::from_reader(handler, File::open(path)?)
+    }
+    /// Builds a `Reader` over any `io::Read` source, e.g. a MIDI chunk
+    /// embedded in a larger container or a fixture read from a `Cursor`.
+    pub fn from_reader<R: Read>(handler: &'a mut Handler, mut source: R) -> Result<Reader<'a>, ReaderError> {
+        let mut buf = Vec::new();
+        source.read_to_end(&mut buf)?;
+        Ok(Reader {
+            handler: handler,
+            buf: buf,
+            pos: 0,
+            time_base: 480,
+            running_status: 0,
+            tick: 0,
+            seconds: 0f64,
+            tempo_map: vec![(0, DEFAULT_TEMPO)],
+            tempo_idx: 0,
+        })
+    }
+    /// Builds a `Reader` directly over an in-memory byte slice.
+    pub fn from_bytes(handler: &'a mut Handler, bytes: &[u8]) -> Result<Reader<'a>, ReaderError> {
+        Reader::from_reader(handler, bytes)
+    }
+    pub fn read(&mut self) -> Result<(), ReaderError> {
+        self.read_header_and_tracks(true)
+    }
+    /// Shared by `read` and `build_tempo_map`'s prescan. The prescan skips
+    /// rebuilding `tempo_map` (it would otherwise recurse forever) since it
+    /// only cares about tick positions, which don't depend on tempo.
+    fn read_header_and_tracks(&mut self, build_map: bool) -> Result<(), ReaderError> {
+        let (_format, track, time_base) = self.read_header()?;
+        self.time_base = time_base;
+        if build_map {
+            self.tempo_map = build_tempo_map(&self.buf)?;
+        }
+        self.handler.header(_format, track, time_base);
+        for i in 0..track {
+            if i > 0 {
+                self.handler.track_change();
+            }
+            self.read_track()?;
+        }
+        Ok(())
+    }
+    fn read_header(&mut self) -> Result<(u16, u16, u16), ReaderError> {
+        let tag = self.read_tag()?;
+        if Tag::new(&tag) != Some(Tag::Header) {
+            return Err(ReaderError::InvalidTag { tag: tag });
+        }
+        let _length = self.read_u32()?;
+        let format = self.read_u16()?;
+        let track = self.read_u16()?;
+        let time_base = self.read_u16()?;
+        Ok((format, track, time_base))
+    }
+    fn read_track(&mut self) -> Result<(), ReaderError> {
+        let tag = self.read_tag()?;
+        if Tag::new(&tag) != Some(Tag::Track) {
+            return Err(ReaderError::InvalidTag { tag: tag });
+        }
+        let length = self.read_u32()? as usize;
+        let end = self.pos + length;
+        // tick/seconds are relative to the track, but tempo_map is a
+        // whole-file timeline (see its doc comment), so only tempo_idx's
+        // position within it resets here.
+        self.running_status = 0;
+        self.tick = 0;
+        self.seconds = 0f64;
+        self.tempo_idx = 0;
+        while self.pos < end {
+            self.read_event()?;
+        }
+        Ok(())
+    }
+    fn read_event(&mut self) -> Result<(), ReaderError> {
+        let delta_time = self.read_vlq()?;
+        let from_tick = self.tick;
+        self.tick += delta_time;
+        self.seconds += self.ticks_to_seconds(from_tick, self.tick);
+        let status = self.peek_u8()?;
+        if status & 0x80 != 0 {
+            self.pos += 1;
+            if status != 0xff && status != 0xf0 && status != 0xf7 && !is_system_real_time(status) {
+                self.running_status = status;
+            }
+        }
+        let status = if status & 0x80 != 0 { status } else { self.running_status };
+        match status {
+            0xff => {
+                let event = MetaEvent::new(self.read_u8()?);
+                let length = self.read_vlq()? as usize;
+                let data = self.read_bytes(length)?;
+                // Meta events cancel running status, same as SysEx.
+                self.running_status = 0;
+                self.handler.meta_event(delta_time, &event, &data);
+                self.handler.meta_event_at(self.tick, self.seconds, &event, &data);
+            }
+            0xf0 | 0xf7 => {
+                let event = SysExEvent::new(status);
+                let length = self.read_vlq()? as usize;
+                let data = self.read_bytes(length)?;
+                self.running_status = 0;
+                self.handler.sys_ex_event(delta_time, &event, &data);
+                self.handler.sys_ex_event_at(self.tick, self.seconds, &event, &data);
+            }
+            0xf1 | 0xf2 | 0xf3 | 0xf6 | 0xf8 | 0xfa | 0xfb | 0xfc | 0xfe => {
+                let event = self.read_system_event(status)?;
+                // System Common messages (unlike Real-Time ones) aren't
+                // channel voice messages, so they don't extend running
+                // status to later events.
+                if status < 0xf8 {
+                    self.running_status = 0;
+                }
+                self.handler.system_event(delta_time, &event);
+                self.handler.system_event_at(self.tick, self.seconds, &event);
+            }
+            _ => {
+                let mut builder = MidiEventBuilder::new(status);
+                while builder.shortage() > 0 {
+                    let byte = self.peek_u8()?;
+                    if is_system_real_time(byte) {
+                        self.pos += 1;
+                        let event = self.read_system_event(byte)?;
+                        self.handler.system_event(0, &event);
+                        self.handler.system_event_at(self.tick, self.seconds, &event);
+                        continue;
+                    }
+                    builder.push(self.read_u8()?);
+                }
+                let event = builder.build();
+                self.handler.midi_event(delta_time, &event);
+                self.handler.midi_event_at(self.tick, self.seconds, &event);
+            }
+        }
+        Ok(())
+    }
+    fn read_system_event(&mut self, status: u8) -> Result<SystemEvent, ReaderError> {
+        use messages::SystemEvent::*;
+        Ok(match status {
+            0xf1 => {
+                let byte = self.read_u8()?;
+                MIDITimeCodeQuarterFrame {
+                    message_type: (byte >> 4) & 0x07,
+                    value: byte & 0x0f,
+                }
+            }
+            0xf2 => {
+                let lsb = self.read_u8()? as u16;
+                let msb = self.read_u8()? as u16;
+                SongPositionPointer { position: (msb << 7) | lsb }
+            }
+            0xf3 => SongSelect { song: self.read_u8()? },
+            0xf6 => TuneRequest,
+            0xf8 => TimingClock,
+            0xfa => Start,
+            0xfb => Continue,
+            0xfc => Stop,
+            0xfe => ActiveSensing,
+            _ => Unknown { status: status, data: Vec::new() },
+        })
+    }
+    /// Converts the tick span `[from_tick, to_tick)` to wall-clock seconds,
+    /// using the fixed SMPTE frame rate (SMPTE time base, signalled by the
+    /// high bit of `time_base`) or, for ticks-per-quarter time bases,
+    /// `tempo_map` -- walking it one segment at a time so a tempo change
+    /// that lands inside the span (set by this track or an earlier one) is
+    /// applied starting exactly at its tick rather than at the next event.
+    fn ticks_to_seconds(&mut self, from_tick: u32, to_tick: u32) -> f64 {
+        if self.time_base & 0x8000 != 0 {
+            let frames_per_second = -((self.time_base >> 8) as i8) as f64;
+            let ticks_per_frame = (self.time_base & 0xff) as f64;
+            return (to_tick - from_tick) as f64 / (frames_per_second * ticks_per_frame);
+        }
+        let ticks_per_quarter = self.time_base as f64;
+        let mut seconds = 0f64;
+        let mut cursor = from_tick;
+        while cursor < to_tick {
+            while self.tempo_idx + 1 < self.tempo_map.len() && self.tempo_map[self.tempo_idx + 1].0 <= cursor {
+                self.tempo_idx += 1;
+            }
+            let segment_end = if self.tempo_idx + 1 < self.tempo_map.len() {
+                self.tempo_map[self.tempo_idx + 1].0.min(to_tick)
+            } else {
+                to_tick
+            };
+            let tempo = self.tempo_map[self.tempo_idx].1;
+            let ticks = (segment_end - cursor) as f64;
+            seconds += (ticks / ticks_per_quarter) * (tempo as f64 / 1_000_000f64);
+            cursor = segment_end;
+        }
+        seconds
+    }
+    fn read_tag(&mut self) -> Result<[u8; 4], ReaderError> {
+        let bytes = self.read_bytes(4)?;
+        let mut tag = [0u8; 4];
+        tag.copy_from_slice(&bytes);
+        Ok(tag)
+    }
+    fn read_u8(&mut self) -> Result<u8, ReaderError> {
+        let byte = self.peek_u8()?;
+        self.pos += 1;
+        Ok(byte)
+    }
+    fn peek_u8(&self) -> Result<u8, ReaderError> {
+        self.buf.get(self.pos).cloned().ok_or(ReaderError::UnexpectedEnd)
+    }
+    fn read_u16(&mut self) -> Result<u16, ReaderError> {
+        let bytes = self.read_bytes(2)?;
+        Ok(((bytes[0] as u16) << 8) | bytes[1] as u16)
+    }
+    fn read_u32(&mut self) -> Result<u32, ReaderError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(
+            ((bytes[0] as u32) << 24) | ((bytes[1] as u32) << 16) | ((bytes[2] as u32) << 8) |
+                bytes[3] as u32,
+        )
+    }
+    fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, ReaderError> {
+        if self.pos + length > self.buf.len() {
+            return Err(ReaderError::UnexpectedEnd);
+        }
+        let bytes = self.buf[self.pos..self.pos + length].to_vec();
+        self.pos += length;
+        Ok(bytes)
+    }
+    fn read_vlq(&mut self) -> Result<u32, ReaderError> {
+        let mut value: u32 = 0;
+        loop {
+            let byte = self.read_u8()?;
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use messages::*;
+    use writer::Writer;
+
+    struct SecondsCapture {
+        note_on_seconds: Vec<f64>,
+    }
+    impl Handler for SecondsCapture {
+        fn header(&mut self, _format: u16, _track: u16, _time_base: u16) {}
+        fn meta_event(&mut self, _delta_time: u32, _event: &MetaEvent, _data: &Vec<u8>) {}
+        fn midi_event(&mut self, _delta_time: u32, _event: &MidiEvent) {}
+        fn sys_ex_event(&mut self, _delta_time: u32, _event: &SysExEvent, _data: &Vec<u8>) {}
+        fn track_change(&mut self) {}
+        fn midi_event_at(&mut self, _tick: u32, seconds: f64, event: &MidiEvent) {
+            if let MidiEvent::NoteOn { .. } = *event {
+                self.note_on_seconds.push(seconds);
+            }
+        }
+    }
+
+    // A tempo set in the conductor track (track 0) must carry over to the
+    // events of every later track, since tempo is global in a format-1 file
+    // rather than per-track.
+    #[test]
+    fn tempo_carries_over_from_conductor_track_to_later_tracks() {
+        let mut writer = Writer::new();
+        writer.time_base(480);
+        writer
+            .push(Message::MetaEvent {
+                delta_time: 0,
+                event: MetaEvent::SetTempo,
+                data: vec![0x03, 0xd0, 0x90], // 250000us/quarter
+            })
+            .push(Message::MetaEvent {
+                delta_time: 0,
+                event: MetaEvent::EndOfTrack,
+                data: Vec::new(),
+            })
+            .push(Message::TrackChange)
+            .push(Message::MidiEvent {
+                delta_time: 480,
+                event: MidiEvent::NoteOn { ch: 0, note: 60, velocity: 64 },
+            })
+            .push(Message::MetaEvent {
+                delta_time: 0,
+                event: MetaEvent::EndOfTrack,
+                data: Vec::new(),
+            });
+        let bytes = writer.to_bytes();
+
+        let mut handler = SecondsCapture { note_on_seconds: Vec::new() };
+        {
+            let mut reader = Reader::from_bytes(&mut handler, &bytes).unwrap();
+            reader.read().unwrap();
+        }
+        assert_eq!(handler.note_on_seconds, vec![0.25]);
+    }
+
+    // A tempo change recorded in the conductor track must be applied to a
+    // later track's events starting exactly at its tick, even when that
+    // tick falls in the middle of the later track's own delta time rather
+    // than lining up with one of its events.
+    #[test]
+    fn tempo_change_mid_delta_is_honored_by_other_tracks() {
+        let mut writer = Writer::new();
+        writer.time_base(480);
+        writer
+            .push(Message::MetaEvent {
+                delta_time: 0,
+                event: MetaEvent::SetTempo,
+                data: vec![0x07, 0xa1, 0x20], // 500000us/quarter
+            })
+            .push(Message::MetaEvent {
+                delta_time: 480,
+                event: MetaEvent::SetTempo,
+                data: vec![0x03, 0xd0, 0x90], // 250000us/quarter
+            })
+            .push(Message::MetaEvent {
+                delta_time: 0,
+                event: MetaEvent::EndOfTrack,
+                data: Vec::new(),
+            })
+            .push(Message::TrackChange)
+            .push(Message::MidiEvent {
+                delta_time: 600,
+                event: MidiEvent::NoteOn { ch: 0, note: 60, velocity: 64 },
+            })
+            .push(Message::MetaEvent {
+                delta_time: 0,
+                event: MetaEvent::EndOfTrack,
+                data: Vec::new(),
+            });
+        let bytes = writer.to_bytes();
+
+        let mut handler = SecondsCapture { note_on_seconds: Vec::new() };
+        {
+            let mut reader = Reader::from_bytes(&mut handler, &bytes).unwrap();
+            reader.read().unwrap();
+        }
+        // 480 ticks @ 500000us/quarter + 120 ticks @ 250000us/quarter.
+        assert_eq!(handler.note_on_seconds, vec![0.5625]);
+    }
+
+    // An SMPTE time base (signalled by the high bit of time_base) converts
+    // ticks to seconds via its fixed frame rate, not the tempo map.
+    #[test]
+    fn smpte_time_base_converts_ticks_to_seconds_via_frame_rate() {
+        let mut writer = Writer::new();
+        writer.time_base(0xe250); // -30 fps, 80 subframes per frame
+        writer
+            .push(Message::MidiEvent {
+                delta_time: 2400, // 2400 ticks / (30 * 80 ticks/sec) = 1 second
+                event: MidiEvent::NoteOn { ch: 0, note: 60, velocity: 64 },
+            })
+            .push(Message::MetaEvent {
+                delta_time: 0,
+                event: MetaEvent::EndOfTrack,
+                data: Vec::new(),
+            });
+        let bytes = writer.to_bytes();
+
+        let mut handler = SecondsCapture { note_on_seconds: Vec::new() };
+        {
+            let mut reader = Reader::from_bytes(&mut handler, &bytes).unwrap();
+            reader.read().unwrap();
+        }
+        assert_eq!(handler.note_on_seconds, vec![1.0]);
+    }
+
+    /// Assembles a single-track, format-0 SMF around raw, already-encoded
+    /// track bytes, so tests can drop in status/data bytes `Writer` has no
+    /// public API to produce (bare interleaved Real-Time bytes, a dangling
+    /// running status byte, ...).
+    fn build_smf(time_base: u16, track_bytes: &[u8]) -> Vec<u8> {
+        let mut binary = Vec::new();
+        binary.extend_from_slice(&Tag::Header.binary());
+        binary.extend_from_slice(&[0, 0, 0, 6]);
+        binary.extend_from_slice(&[0, 0, 0, 1]);
+        binary.push((time_base >> 8) as u8);
+        binary.push(time_base as u8);
+        binary.extend_from_slice(&Tag::Track.binary());
+        let length = track_bytes.len() as u32;
+        binary.extend_from_slice(&[
+            (length >> 24) as u8,
+            (length >> 16) as u8,
+            (length >> 8) as u8,
+            length as u8,
+        ]);
+        binary.extend_from_slice(track_bytes);
+        binary
+    }
+
+    struct EventCapture {
+        note_on_notes: Vec<u8>,
+        system_events: Vec<SystemEvent>,
+    }
+    impl Handler for EventCapture {
+        fn header(&mut self, _format: u16, _track: u16, _time_base: u16) {}
+        fn meta_event(&mut self, _delta_time: u32, _event: &MetaEvent, _data: &Vec<u8>) {}
+        fn midi_event(&mut self, _delta_time: u32, event: &MidiEvent) {
+            if let MidiEvent::NoteOn { note, .. } = *event {
+                self.note_on_notes.push(note);
+            }
+        }
+        fn sys_ex_event(&mut self, _delta_time: u32, _event: &SysExEvent, _data: &Vec<u8>) {}
+        fn track_change(&mut self) {}
+        fn system_event(&mut self, _delta_time: u32, event: &SystemEvent) {
+            self.system_events.push(event.clone());
+        }
+    }
+
+    // A System Common message standalone at the top level of a track must
+    // decode like any other event.
+    #[test]
+    fn standalone_system_common_message_is_read() {
+        let bytes = build_smf(
+            480,
+            &[
+                0x00, 0xf3, 0x05, // SongSelect, song 5
+                0x00, 0xff, 0x2f, 0x00, // EndOfTrack
+            ],
+        );
+        let mut handler = EventCapture { note_on_notes: Vec::new(), system_events: Vec::new() };
+        {
+            let mut reader = Reader::from_bytes(&mut handler, &bytes).unwrap();
+            reader.read().unwrap();
+        }
+        assert_eq!(handler.system_events, vec![SystemEvent::SongSelect { song: 5 }]);
+    }
+
+    // A Real-Time byte interleaved inside another message's data bytes must
+    // be pulled out and dispatched on its own, without disturbing the
+    // message it interrupted.
+    #[test]
+    fn real_time_byte_interleaved_inside_another_events_data_bytes() {
+        let bytes = build_smf(
+            480,
+            &[
+                0x00, 0x90, 60, // NoteOn ch0 note60, velocity not read yet
+                0xf8, // TimingClock, interleaved before the velocity byte
+                64, // the interrupted NoteOn's velocity byte
+                0x00, 0xff, 0x2f, 0x00, // EndOfTrack
+            ],
+        );
+        let mut handler = EventCapture { note_on_notes: Vec::new(), system_events: Vec::new() };
+        {
+            let mut reader = Reader::from_bytes(&mut handler, &bytes).unwrap();
+            reader.read().unwrap();
+        }
+        assert_eq!(handler.note_on_notes, vec![60]);
+        assert_eq!(handler.system_events, vec![SystemEvent::TimingClock]);
+    }
+
+    // A Real-Time byte interleaved at a message boundary -- not nested
+    // inside another event's data bytes, but standalone between two
+    // running-status-encoded events -- must not disturb running status, so
+    // the event that follows it is still decoded correctly.
+    #[test]
+    fn real_time_byte_at_message_boundary_does_not_disturb_running_status() {
+        let bytes = build_smf(
+            480,
+            &[
+                0x00, 0x90, 60, 64, // NoteOn ch0 note60 vel64, establishes running status
+                0x00, 0xf8, // TimingClock, standalone between two events
+                0x00, 62, 64, // running-status continuation: NoteOn note62 vel64
+                0x00, 0xff, 0x2f, 0x00, // EndOfTrack
+            ],
+        );
+        let mut handler = EventCapture { note_on_notes: Vec::new(), system_events: Vec::new() };
+        {
+            let mut reader = Reader::from_bytes(&mut handler, &bytes).unwrap();
+            reader.read().unwrap();
+        }
+        assert_eq!(handler.note_on_notes, vec![60, 62]);
+        assert_eq!(handler.system_events, vec![SystemEvent::TimingClock]);
+    }
+
+    // A Meta event between two running-status-encoded events must cancel
+    // running status, just like SysEx does, so the status-less data bytes
+    // that follow it aren't decoded as another NoteOn.
+    #[test]
+    fn meta_event_cancels_running_status() {
+        let bytes = build_smf(
+            480,
+            &[
+                0x00, 0x90, 60, 64, // NoteOn ch0 note60 vel64, establishes running status
+                0x00, 0xff, 0x20, 0x01, 0x00, // MIDIChannelPrefix meta event, channel 0
+                0x00, 62, 64, // no status byte: must not reuse the NoteOn status
+                0x00, 0xff, 0x2f, 0x00, // EndOfTrack, status byte given explicitly
+            ],
+        );
+        let mut handler = EventCapture { note_on_notes: Vec::new(), system_events: Vec::new() };
+        {
+            let mut reader = Reader::from_bytes(&mut handler, &bytes).unwrap();
+            reader.read().unwrap();
+        }
+        assert_eq!(handler.note_on_notes, vec![60]);
+    }
+}
@@ -0,0 +1,262 @@
+//! A structured, absolutely-timed multi-track SMF model built on top of
+//! the flat, delta-time [`Message`](::messages) stream `Reader`/`Writer`
+//! operate on. Useful for editing a file's events (sorting, merging,
+//! splitting) without having to re-derive absolute time by hand.
+
+use std::collections::BTreeMap;
+
+use formats::*;
+use messages::*;
+
+/// A single `MetaEvent`/`MidiEvent`/`SysExEvent`/`SystemEvent`, without the
+/// delta time a `Message` carries (a `Track` associates each event with an
+/// absolute tick instead).
+#[derive(PartialEq, Clone, Debug)]
+pub enum TrackEvent {
+    Meta { event: MetaEvent, data: Vec<u8> },
+    Midi { event: MidiEvent },
+    SysEx { event: SysExEvent, data: Vec<u8> },
+    System { event: SystemEvent },
+}
+impl TrackEvent {
+    fn to_message(&self, delta_time: VLQ) -> Message {
+        match *self {
+            TrackEvent::Meta { ref event, ref data } => {
+                Message::MetaEvent {
+                    delta_time: delta_time,
+                    event: event.clone(),
+                    data: data.clone(),
+                }
+            }
+            TrackEvent::Midi { ref event } => {
+                Message::MidiEvent {
+                    delta_time: delta_time,
+                    event: event.clone(),
+                }
+            }
+            TrackEvent::SysEx { ref event, ref data } => {
+                Message::SysExEvent {
+                    delta_time: delta_time,
+                    event: event.clone(),
+                    data: data.clone(),
+                }
+            }
+            TrackEvent::System { ref event } => {
+                Message::SystemEvent {
+                    delta_time: delta_time,
+                    event: event.clone(),
+                }
+            }
+        }
+    }
+}
+
+/// One track's events, each keyed by the absolute tick it occurs at.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Track {
+    pub events: Vec<(u32, TrackEvent)>,
+}
+impl Track {
+    pub fn new() -> Track {
+        Track { events: Vec::new() }
+    }
+    pub fn push(&mut self, tick: u32, event: TrackEvent) -> &mut Track {
+        self.events.push((tick, event));
+        self
+    }
+    /// Converts to the flat delta-time `Message` stream a `Writer`
+    /// consumes: stable-sorted by tick (so simultaneous events keep their
+    /// relative order), with exactly one trailing `EndOfTrack`.
+    pub fn to_messages(&self) -> Vec<Message> {
+        let mut events = self.events.clone();
+        events.sort_by_key(|&(tick, _)| tick);
+
+        let mut messages = Vec::new();
+        let mut last_tick = 0u32;
+        let mut end_tick = 0u32;
+        for (tick, event) in events {
+            if let TrackEvent::Meta { ref event, .. } = event {
+                if *event == MetaEvent::EndOfTrack {
+                    end_tick = end_tick.max(tick);
+                    continue;
+                }
+            }
+            messages.push(event.to_message(tick - last_tick));
+            last_tick = tick;
+        }
+        messages.push(Message::MetaEvent {
+            delta_time: end_tick.max(last_tick) - last_tick,
+            event: MetaEvent::EndOfTrack,
+            data: Vec::new(),
+        });
+        messages
+    }
+}
+
+/// A whole file: the header fields `Writer`/`Reader` exchange over `MThd`,
+/// plus its tracks.
+#[derive(PartialEq, Clone, Debug)]
+pub struct SmfFile {
+    pub format: u16,
+    pub time_base: u16,
+    pub tracks: Vec<Track>,
+}
+impl SmfFile {
+    pub fn new(time_base: u16) -> SmfFile {
+        SmfFile {
+            format: 0,
+            time_base: time_base,
+            tracks: Vec::new(),
+        }
+    }
+    pub fn push_track(&mut self, track: Track) -> &mut SmfFile {
+        self.tracks.push(track);
+        self.format = if self.tracks.len() > 1 { 1 } else { 0 };
+        self
+    }
+    /// Flattens every track into the single `Message` stream a `Writer`
+    /// consumes, separated by `Message::TrackChange`.
+    pub fn to_messages(&self) -> Vec<Message> {
+        let mut messages = Vec::new();
+        for (i, track) in self.tracks.iter().enumerate() {
+            if i > 0 {
+                messages.push(Message::TrackChange);
+            }
+            messages.extend(track.to_messages());
+        }
+        messages
+    }
+    /// Merges every track into a single format-0 track: all events are
+    /// interleaved sorted by absolute tick (stable, so simultaneous events
+    /// keep their original relative order), delta times are recomputed by
+    /// `Track::to_messages`, and every `EndOfTrack` is dropped so that
+    /// `Track::to_messages` emits exactly one final one.
+    pub fn to_format_0(&self) -> SmfFile {
+        let mut merged: Vec<(u32, TrackEvent)> = Vec::new();
+        for track in &self.tracks {
+            for &(tick, ref event) in &track.events {
+                if let TrackEvent::Meta { ref event, .. } = *event {
+                    if *event == MetaEvent::EndOfTrack {
+                        continue;
+                    }
+                }
+                merged.push((tick, event.clone()));
+            }
+        }
+        merged.sort_by_key(|&(tick, _)| tick);
+        SmfFile {
+            format: 0,
+            time_base: self.time_base,
+            tracks: vec![Track { events: merged }],
+        }
+    }
+    /// Splits a single-track file's events into one track per MIDI channel
+    /// in use, keeping non-channel events (meta, sysex, system) in their
+    /// own leading track. Files that already have more than one track are
+    /// returned unchanged.
+    pub fn split_by_channel(&self) -> SmfFile {
+        if self.tracks.len() != 1 {
+            return self.clone();
+        }
+        let mut shared = Track::new();
+        let mut by_channel: BTreeMap<u8, Track> = BTreeMap::new();
+        for &(tick, ref event) in &self.tracks[0].events {
+            match *event {
+                TrackEvent::Midi { ref event } => {
+                    by_channel
+                        .entry(event.channel())
+                        .or_insert_with(Track::new)
+                        .push(tick, TrackEvent::Midi { event: event.clone() });
+                }
+                TrackEvent::Meta { ref event, .. } if *event == MetaEvent::EndOfTrack => {}
+                _ => {
+                    shared.push(tick, event.clone());
+                }
+            }
+        }
+        let mut tracks = Vec::new();
+        if !shared.events.is_empty() {
+            tracks.push(shared);
+        }
+        tracks.extend(by_channel.into_iter().map(|(_, track)| track));
+        SmfFile {
+            format: if tracks.len() > 1 { 1 } else { 0 },
+            time_base: self.time_base,
+            tracks: tracks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note_on(ch: u8, note: u8) -> TrackEvent {
+        TrackEvent::Midi { event: MidiEvent::NoteOn { ch: ch, note: note, velocity: 64 } }
+    }
+
+    #[test]
+    fn to_format_0_merges_tracks_sorted_by_tick_with_one_trailing_end_of_track() {
+        let mut track0 = Track::new();
+        track0.push(0, note_on(0, 60));
+        track0.push(480, TrackEvent::Meta { event: MetaEvent::EndOfTrack, data: Vec::new() });
+        let mut track1 = Track::new();
+        track1.push(240, note_on(1, 64));
+        track1.push(480, TrackEvent::Meta { event: MetaEvent::EndOfTrack, data: Vec::new() });
+
+        let mut file = SmfFile::new(480);
+        file.push_track(track0);
+        file.push_track(track1);
+        assert_eq!(file.format, 1);
+
+        let merged = file.to_format_0();
+        assert_eq!(merged.format, 0);
+        assert_eq!(merged.tracks.len(), 1);
+        assert_eq!(
+            merged.tracks[0].events,
+            vec![(0, note_on(0, 60)), (240, note_on(1, 64))]
+        );
+
+        let messages = merged.to_messages();
+        assert_eq!(messages.len(), 3);
+        match messages[2] {
+            Message::MetaEvent { ref event, .. } => assert_eq!(*event, MetaEvent::EndOfTrack),
+            ref other => panic!("expected trailing EndOfTrack, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn split_by_channel_derives_format_from_resulting_track_count() {
+        let mut track = Track::new();
+        track.push(0, note_on(0, 60));
+        track.push(480, note_on(0, 64));
+        track.push(960, TrackEvent::Meta { event: MetaEvent::EndOfTrack, data: Vec::new() });
+        let mut file = SmfFile::new(480);
+        file.push_track(track);
+        assert_eq!(file.format, 0);
+
+        let split = file.split_by_channel();
+        assert_eq!(split.tracks.len(), 1);
+        assert_eq!(split.format, 0);
+    }
+
+    #[test]
+    fn split_by_channel_then_to_format_0_round_trips_channel_events() {
+        let mut track = Track::new();
+        track.push(0, note_on(0, 60));
+        track.push(0, note_on(1, 67));
+        track.push(480, TrackEvent::Meta { event: MetaEvent::EndOfTrack, data: Vec::new() });
+        let mut file = SmfFile::new(480);
+        file.push_track(track);
+
+        let split = file.split_by_channel();
+        assert_eq!(split.tracks.len(), 2);
+        assert_eq!(split.format, 1);
+
+        let merged = split.to_format_0();
+        assert_eq!(
+            merged.tracks[0].events,
+            vec![(0, note_on(0, 60)), (0, note_on(1, 67))]
+        );
+    }
+}
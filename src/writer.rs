@@ -0,0 +1,405 @@
+//! Standard MIDI File writer: assembles pushed [`Message`](::messages)s
+//! into a valid SMF byte stream.
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io;
+use std::io::Write as IoWrite;
+use std::path::Path;
+
+use formats::*;
+use messages::*;
+
+/// A structural problem found by [`Writer::validate`]/[`Writer::fix`] in
+/// the pushed messages, identified by the index of the track (counting
+/// `TrackChange`s) it occurs in.
+#[derive(PartialEq, Clone, Debug)]
+pub enum LintWarning {
+    /// The track didn't end with an `EndOfTrack` meta event.
+    MissingEndOfTrack { track: usize },
+    /// A message was pushed after the track's `EndOfTrack`.
+    EventAfterEndOfTrack { track: usize },
+    /// A `NoteOn` had no matching `NoteOff` (or `NoteOn` with velocity 0)
+    /// before the end of the track.
+    DanglingNoteOn { track: usize, channel: u8, note: u8 },
+    /// A note/velocity/control/program data byte exceeded 0x7f.
+    ByteOutOfRange { track: usize, field: &'static str, value: u8 },
+    /// A pitch bend value fell outside the -8192..=8191 range.
+    PitchBendOutOfRange { track: usize, channel: u8, value: i16 },
+}
+
+fn clamp_data_byte(value: u8) -> u8 {
+    if value > 0x7f { 0x7f } else { value }
+}
+
+/// Emits the `LintWarning`s for the end of a track (dangling notes, a
+/// missing `EndOfTrack`), inserting a synthesized `EndOfTrack` into
+/// `fixed_messages` when it's given and one is missing.
+fn lint_track_end(
+    warnings: &mut Vec<LintWarning>,
+    fixed_messages: &mut Option<&mut Vec<Message>>,
+    track: usize,
+    ended: bool,
+    open_notes: &BTreeSet<(u8, u8)>,
+) {
+    // Iterated in `(channel, note)` order, not insertion order, so warnings
+    // come out deterministic for golden-file diffing and stable reporting.
+    for &(channel, note) in open_notes {
+        warnings.push(LintWarning::DanglingNoteOn { track: track, channel: channel, note: note });
+    }
+    if !ended {
+        warnings.push(LintWarning::MissingEndOfTrack { track: track });
+        if let Some(ref mut fixed_messages) = *fixed_messages {
+            fixed_messages.push(Message::MetaEvent {
+                delta_time: 0,
+                event: MetaEvent::EndOfTrack,
+                data: Vec::new(),
+            });
+        }
+    }
+}
+
+/// Checks (and, if `autofix`, clamps) the data bytes of a single MIDI
+/// event, pushing a `LintWarning` for anything out of range.
+fn lint_midi_event(event: &mut MidiEvent, track: usize, warnings: &mut Vec<LintWarning>, autofix: bool) {
+    let check = |field: &'static str, value: u8, warnings: &mut Vec<LintWarning>| -> u8 {
+        if value > 0x7f {
+            warnings.push(LintWarning::ByteOutOfRange { track: track, field: field, value: value });
+            if autofix { clamp_data_byte(value) } else { value }
+        } else {
+            value
+        }
+    };
+    match *event {
+        MidiEvent::NoteOn { ref mut note, ref mut velocity, .. } |
+        MidiEvent::NoteOff { ref mut note, ref mut velocity, .. } |
+        MidiEvent::PolyphonicKeyPressure { ref mut note, ref mut velocity, .. } => {
+            *note = check("note", *note, warnings);
+            *velocity = check("velocity", *velocity, warnings);
+        }
+        MidiEvent::ControlChange { ref mut control, ref mut data, .. } => {
+            *control = check("control", *control, warnings);
+            *data = check("data", *data, warnings);
+        }
+        MidiEvent::ProgramChange { ref mut program, .. } => {
+            *program = check("program", *program, warnings);
+        }
+        MidiEvent::ChannelPressure { ref mut pressure, .. } => {
+            *pressure = check("pressure", *pressure, warnings);
+        }
+        MidiEvent::PitchBendChange { ch, ref mut data } => {
+            if *data < -8192 || *data > 8191 {
+                warnings.push(
+                    LintWarning::PitchBendOutOfRange { track: track, channel: ch, value: *data },
+                );
+                if autofix {
+                    *data = (*data).max(-8192).min(8191);
+                }
+            }
+        }
+        MidiEvent::ChannelMode { ref mut mode, .. } => {
+            match *mode {
+                ChannelMode::AllSoundOff { ref mut data } |
+                ChannelMode::ResetAllControllers { ref mut data } |
+                ChannelMode::LocalControl { ref mut data } |
+                ChannelMode::AllNotesOff { ref mut data } |
+                ChannelMode::OmniOff { ref mut data } |
+                ChannelMode::OmniOn { ref mut data } |
+                ChannelMode::PolyMode { ref mut data } => {
+                    *data = check("channel mode data", *data, warnings);
+                }
+                ChannelMode::MonoMode { ref mut channel_count } => {
+                    *channel_count = check("channel mode data", *channel_count, warnings);
+                }
+            }
+        }
+        MidiEvent::Unknown { .. } => {}
+    }
+}
+
+pub struct Writer {
+    messages: Vec<Message>,
+    running_status: bool,
+    time_base: u16,
+}
+impl Writer {
+    pub fn new() -> Writer {
+        Writer {
+            messages: Vec::new(),
+            running_status: false,
+            time_base: 480,
+        }
+    }
+    pub fn running_status(&mut self, running_status: bool) -> &mut Writer {
+        self.running_status = running_status;
+        self
+    }
+    pub fn time_base(&mut self, time_base: u16) -> &mut Writer {
+        self.time_base = time_base;
+        self
+    }
+    pub fn push(&mut self, message: Message) -> &mut Writer {
+        self.messages.push(message);
+        self
+    }
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(&mut File::create(path)?)
+    }
+    /// Writes the assembled SMF to any `io::Write` sink.
+    pub fn write_to<W: IoWrite>(&self, sink: &mut W) -> io::Result<()> {
+        sink.write_all(&self.to_bytes())
+    }
+    /// Assembles the pushed messages into a complete, in-memory SMF.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let tracks = self.track_binaries();
+        let format: u16 = if tracks.len() > 1 { 1 } else { 0 };
+        let mut binary = Vec::new();
+        binary.extend_from_slice(&Tag::Header.binary());
+        binary.extend_from_slice(&[0, 0, 0, 6]);
+        binary.push((format >> 8) as u8);
+        binary.push(format as u8);
+        binary.push((tracks.len() >> 8) as u8);
+        binary.push(tracks.len() as u8);
+        binary.push((self.time_base >> 8) as u8);
+        binary.push(self.time_base as u8);
+        for track in tracks {
+            binary.extend_from_slice(&Tag::Track.binary());
+            let length = track.len() as u32;
+            binary.push((length >> 24) as u8);
+            binary.push((length >> 16) as u8);
+            binary.push((length >> 8) as u8);
+            binary.push(length as u8);
+            binary.extend(track);
+        }
+        binary
+    }
+    /// Checks the pushed messages for structural problems that would
+    /// produce an invalid SMF (missing `EndOfTrack`, dangling `NoteOn`s,
+    /// out-of-range data bytes, ...) without modifying anything.
+    pub fn validate(&self) -> Result<(), Vec<LintWarning>> {
+        let warnings = self.lint(None);
+        if warnings.is_empty() { Ok(()) } else { Err(warnings) }
+    }
+    /// Like `validate`, but also auto-fixes what can be fixed safely: a
+    /// missing `EndOfTrack` is inserted and out-of-range data bytes are
+    /// clamped to 0x7f (pitch bend to -8192..=8191). Issues that can't be fixed
+    /// without guessing at intent, e.g. a dangling `NoteOn`, are left
+    /// alone. Returns every warning found, fixed or not.
+    pub fn fix(&mut self) -> Vec<LintWarning> {
+        let mut fixed_messages = Vec::new();
+        let warnings = self.lint(Some(&mut fixed_messages));
+        self.messages = fixed_messages;
+        warnings
+    }
+    /// Single pass over `self.messages` that both collects `LintWarning`s
+    /// and, when `fixed_messages` is given, writes out an auto-fixed copy
+    /// of the message stream.
+    fn lint(&self, mut fixed_messages: Option<&mut Vec<Message>>) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        let mut track = 0usize;
+        let mut open_notes: BTreeSet<(u8, u8)> = BTreeSet::new();
+        let mut ended = false;
+        let autofix = fixed_messages.is_some();
+
+        for message in &self.messages {
+            if let Message::TrackChange = *message {
+                lint_track_end(&mut warnings, &mut fixed_messages, track, ended, &open_notes);
+                open_notes.clear();
+                ended = false;
+                track += 1;
+                if let Some(ref mut fixed_messages) = fixed_messages {
+                    fixed_messages.push(message.clone());
+                }
+                continue;
+            }
+
+            if ended {
+                warnings.push(LintWarning::EventAfterEndOfTrack { track: track });
+            }
+
+            let mut message = message.clone();
+            if let Message::MetaEvent { ref event, .. } = message {
+                if *event == MetaEvent::EndOfTrack {
+                    ended = true;
+                }
+            }
+            if let Message::MidiEvent { ref mut event, .. } = message {
+                lint_midi_event(event, track, &mut warnings, autofix);
+                match *event {
+                    MidiEvent::NoteOn { ch, note, velocity } => {
+                        if velocity == 0 {
+                            open_notes.remove(&(ch, note));
+                        } else {
+                            open_notes.insert((ch, note));
+                        }
+                    }
+                    MidiEvent::NoteOff { ch, note, .. } => {
+                        open_notes.remove(&(ch, note));
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(ref mut fixed_messages) = fixed_messages {
+                fixed_messages.push(message);
+            }
+        }
+        lint_track_end(&mut warnings, &mut fixed_messages, track, ended, &open_notes);
+        warnings
+    }
+    /// Splits the pushed messages on `Message::TrackChange` and encodes
+    /// each resulting track, suppressing repeated MIDI status bytes when
+    /// `running_status` is enabled.
+    fn track_binaries(&self) -> Vec<Vec<u8>> {
+        let mut tracks: Vec<Vec<u8>> = vec![Vec::new()];
+        let mut running_status_byte: Option<u8> = None;
+        for message in &self.messages {
+            if let Message::TrackChange = *message {
+                tracks.push(Vec::new());
+                running_status_byte = None;
+                continue;
+            }
+            let track = tracks.last_mut().unwrap();
+            if self.running_status {
+                if let Message::MidiEvent { delta_time, ref event } = *message {
+                    track.append(&mut delta_time.binary());
+                    let mut event_binary = event.binary();
+                    let status = event.status_byte();
+                    if Some(status) == running_status_byte {
+                        event_binary.remove(0);
+                    } else {
+                        running_status_byte = Some(status);
+                    }
+                    track.append(&mut event_binary);
+                    continue;
+                }
+            }
+            running_status_byte = None;
+            track.append(&mut message.binary());
+        }
+        tracks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use messages::*;
+
+    fn note_on(ch: u8, note: u8, velocity: u8) -> Message {
+        Message::MidiEvent { delta_time: 0, event: MidiEvent::NoteOn { ch: ch, note: note, velocity: velocity } }
+    }
+    fn note_off(ch: u8, note: u8) -> Message {
+        Message::MidiEvent { delta_time: 0, event: MidiEvent::NoteOff { ch: ch, note: note, velocity: 64 } }
+    }
+    fn end_of_track() -> Message {
+        Message::MetaEvent { delta_time: 0, event: MetaEvent::EndOfTrack, data: Vec::new() }
+    }
+
+    #[test]
+    fn fix_inserts_missing_end_of_track() {
+        let mut writer = Writer::new();
+        writer.push(note_on(0, 60, 64)).push(note_off(0, 60));
+
+        let warnings = writer.fix();
+
+        assert_eq!(warnings, vec![LintWarning::MissingEndOfTrack { track: 0 }]);
+        assert_eq!(writer.messages.last(), Some(&end_of_track()));
+    }
+
+    #[test]
+    fn validate_reports_dangling_notes_in_channel_note_order() {
+        let mut writer = Writer::new();
+        writer
+            .push(note_on(1, 64, 64))
+            .push(note_on(0, 72, 64))
+            .push(note_on(0, 60, 64))
+            .push(note_off(0, 60))
+            .push(end_of_track());
+
+        let warnings = writer.validate().unwrap_err();
+
+        // Two notes are left dangling (ch 0/note 72 and ch 1/note 64); they
+        // must come out sorted rather than in the non-deterministic order a
+        // HashSet iteration would produce.
+        assert_eq!(
+            warnings,
+            vec![
+                LintWarning::DanglingNoteOn { track: 0, channel: 0, note: 72 },
+                LintWarning::DanglingNoteOn { track: 0, channel: 1, note: 64 },
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_reports_event_after_end_of_track() {
+        let mut writer = Writer::new();
+        writer.push(note_on(0, 60, 64)).push(note_off(0, 60)).push(end_of_track()).push(note_off(0, 60));
+
+        let warnings = writer.validate().unwrap_err();
+
+        assert_eq!(warnings, vec![LintWarning::EventAfterEndOfTrack { track: 0 }]);
+    }
+
+    #[test]
+    fn fix_clamps_out_of_range_data_bytes() {
+        let mut writer = Writer::new();
+        writer
+            .push(note_on(0, 0xff, 0xff))
+            .push(note_off(0, 0x7f))
+            .push(Message::MidiEvent {
+                delta_time: 0,
+                event: MidiEvent::PitchBendChange { ch: 0, data: 9000 },
+            })
+            .push(end_of_track());
+
+        let warnings = writer.fix();
+
+        assert_eq!(
+            warnings,
+            vec![
+                LintWarning::ByteOutOfRange { track: 0, field: "note", value: 0xff },
+                LintWarning::ByteOutOfRange { track: 0, field: "velocity", value: 0xff },
+                LintWarning::PitchBendOutOfRange { track: 0, channel: 0, value: 9000 },
+            ]
+        );
+        assert_eq!(writer.messages[0], note_on(0, 0x7f, 0x7f));
+        assert_eq!(
+            writer.messages[2],
+            Message::MidiEvent { delta_time: 0, event: MidiEvent::PitchBendChange { ch: 0, data: 8191 } }
+        );
+    }
+
+    struct NoteOnCapture {
+        notes: Vec<u8>,
+    }
+    impl ::reader::Handler for NoteOnCapture {
+        fn header(&mut self, _format: u16, _track: u16, _time_base: u16) {}
+        fn meta_event(&mut self, _delta_time: u32, _event: &MetaEvent, _data: &Vec<u8>) {}
+        fn midi_event(&mut self, _delta_time: u32, event: &MidiEvent) {
+            if let MidiEvent::NoteOn { note, .. } = *event {
+                self.notes.push(note);
+            }
+        }
+        fn sys_ex_event(&mut self, _delta_time: u32, _event: &SysExEvent, _data: &Vec<u8>) {}
+        fn track_change(&mut self) {}
+    }
+
+    // write_to/from_reader must work over an arbitrary io::Write/io::Read
+    // pair, e.g. a MIDI chunk embedded in a larger container, not just a
+    // File.
+    #[test]
+    fn write_to_and_from_reader_round_trip_through_a_cursor() {
+        let mut writer = Writer::new();
+        writer.push(note_on(0, 60, 64)).push(note_off(0, 60)).push(end_of_track());
+
+        let mut sink = io::Cursor::new(Vec::new());
+        writer.write_to(&mut sink).unwrap();
+
+        let mut handler = NoteOnCapture { notes: Vec::new() };
+        {
+            let mut reader = ::reader::Reader::from_reader(&mut handler, io::Cursor::new(sink.into_inner())).unwrap();
+            reader.read().unwrap();
+        }
+        assert_eq!(handler.notes, vec![60]);
+    }
+}